@@ -6,7 +6,8 @@ use std::sync::Arc;
 use std::task::{Context, Poll};
 use std::time::Duration;
 
-use axum::http::Request;
+use axum::body::Bytes;
+use axum::http::{Request, StatusCode};
 use axum::response::Response;
 use futures_util::future::BoxFuture;
 use maxminddb::geoip2;
@@ -14,16 +15,107 @@ use tower::Service;
 
 pub use maxminddb::Reader;
 
-/// Configuration for applying request delays (tariffs) based on IP country.
+/// The action taken for requests matching a tariff.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Action {
+    /// Delay the request by the given duration, then let it through.
+    Delay(Duration),
+    /// Reject the request immediately with the given status code and an optional body,
+    /// without ever calling the inner service.
+    Reject(StatusCode, Option<Bytes>),
+    /// Delay the request, then reject it with the given status code instead of letting it
+    /// through. Useful for tarpitting abusive traffic rather than just throttling it.
+    DelayThen(Duration, StatusCode),
+}
+
+impl Action {
+    /// How severe this action is relative to another, used to pick a winner when a country
+    /// tariff and an ASN tariff both match: rejecting outranks delay-then-reject, which
+    /// outranks a plain delay.
+    fn severity(&self) -> u8 {
+        match self {
+            Action::Delay(_) => 0,
+            Action::DelayThen(..) => 1,
+            Action::Reject(..) => 2,
+        }
+    }
+}
+
+/// Controls how a country tariff and an ASN tariff are combined when both match the same
+/// request.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DelayCombine {
+    /// Apply the larger of the two delays (default).
+    #[default]
+    Max,
+    /// Apply the sum of the two delays.
+    Sum,
+}
+
+impl DelayCombine {
+    fn apply(self, a: Duration, b: Duration) -> Duration {
+        match self {
+            DelayCombine::Max => a.max(b),
+            DelayCombine::Sum => a + b,
+        }
+    }
+}
+
+/// Strategy used to resolve the client's IP address from an incoming request.
+///
+/// Tariffs are only as trustworthy as the IP they key off of, and several of these headers
+/// can be forged by the client unless the deployment is known to sit behind a trusted proxy
+/// that sets (and overwrites) them correctly.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum ClientIpSource {
+    /// Trust only the peer address from `ConnectInfo`. Safe by default, but resolves to the
+    /// proxy's address rather than the real client's when running behind one.
+    #[default]
+    ConnectInfo,
+    /// Take the rightmost entry of the `X-Forwarded-For` header, i.e. the address appended by
+    /// the proxy directly in front of this service.
+    RightmostForwardedFor,
+    /// Take the entry `n` positions from the right of `X-Forwarded-For` (0-based, so
+    /// `TrustedHops(0)` is the same as [`ClientIpSource::RightmostForwardedFor`]) - use this
+    /// when `n + 1` trusted proxies sit in front of this service and each appends to the list.
+    TrustedHops(usize),
+    /// Read the `X-Real-IP` header, as set by proxies such as nginx.
+    XRealIp,
+    /// Parse the `Forwarded` header (RFC 7239) and take the last `for=` token.
+    Forwarded,
+}
+
+/// Configuration for applying request actions (tariffs) based on an IP's resolved location.
 ///
-/// This struct maps ISO country codes to delay durations,
-/// and uses a MaxMind DB to determine the country for a given IP address.
+/// This struct maps ISO country codes (and, optionally, continent codes, subdivision codes,
+/// and city geoname IDs) to [`Action`]s, and uses MaxMind DBs to resolve them for a given IP
+/// address.
 #[derive(Debug)]
 pub struct Config {
-    // Mapping of ISO country codes (e.g., "US", "FR") to delay durations
-    tariffs: HashMap<Box<str>, Duration>,
+    // Mapping of ISO country codes (e.g., "US", "FR") to actions
+    tariffs: HashMap<Box<str>, Action>,
+    // Mapping of continent codes (e.g., "AF", "SA") to actions
+    continent_tariffs: HashMap<Box<str>, Action>,
+    // Mapping of first-level subdivision ISO codes (e.g., "CA" for California) to actions
+    subdivision_tariffs: HashMap<Box<str>, Action>,
+    // Mapping of city geoname IDs to actions
+    city_tariffs: HashMap<u32, Action>,
+    // Mapping of autonomous system numbers to actions
+    asn_tariffs: HashMap<u32, Action>,
     // MaxMind database reader used to look up IP address locations
     reader: Reader<Vec<u8>>,
+    // MaxMind database reader used to look up the autonomous system of an IP address
+    asn_reader: Option<Reader<Vec<u8>>>,
+    // MaxMind City database reader used to look up subdivisions and cities
+    city_reader: Option<Reader<Vec<u8>>>,
+    // How to combine a country/continent/subdivision/city tariff and an ASN tariff when both match
+    asn_combine: DelayCombine,
+    // Where to resolve the client's IP address from
+    ip_source: ClientIpSource,
+    // Action applied when nothing else matches
+    default_action: Option<Action>,
+    // Whether loopback, private, and link-local addresses bypass lookup and tariffs entirely
+    skip_private_ranges: bool,
 }
 
 impl Config {
@@ -42,7 +134,17 @@ impl Config {
     pub fn new(reader: Reader<Vec<u8>>) -> Self {
         Self {
             tariffs: Default::default(),
+            continent_tariffs: Default::default(),
+            subdivision_tariffs: Default::default(),
+            city_tariffs: Default::default(),
+            asn_tariffs: Default::default(),
             reader,
+            asn_reader: None,
+            city_reader: None,
+            asn_combine: DelayCombine::default(),
+            ip_source: ClientIpSource::default(),
+            default_action: None,
+            skip_private_ranges: true,
         }
     }
 
@@ -63,8 +165,173 @@ impl Config {
     ///     .with("US", tokio::time::Duration::from_secs(2))  // Delay US traffic by 2 seconds
     ///     .with("CN", tokio::time::Duration::from_millis(500)); // Delay CN traffic by 500ms
     /// ```
-    pub fn with(mut self, code: &str, delay: Duration) -> Self {
-        self.tariffs.insert(Box::from(code.to_uppercase()), delay);
+    pub fn with(self, code: &str, delay: Duration) -> Self {
+        self.with_action(code, Action::Delay(delay))
+    }
+
+    /// Add a country code and associated [`Action`] to the tariff configuration.
+    ///
+    /// This uses the ISO alpha-2 country code (e.g., "US", "DE", "IN").
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let reader = axum_tariff::Reader::open_readfile("assets/GeoLite2-Country-Test.mmdb").unwrap();
+    /// let config = axum_tariff::Config::new(reader)
+    ///     .with_action("KP", axum_tariff::Action::Reject(axum::http::StatusCode::FORBIDDEN, None));
+    /// ```
+    pub fn with_action(mut self, code: &str, action: Action) -> Self {
+        self.tariffs.insert(Box::from(code.to_uppercase()), action);
+        self
+    }
+
+    /// Provide a GeoLite2-ASN (or GeoIP2-ASN) database reader, enabling tariffs keyed by
+    /// autonomous system number.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let country_reader = axum_tariff::Reader::open_readfile("assets/GeoLite2-Country-Test.mmdb").unwrap();
+    /// let asn_reader = axum_tariff::Reader::open_readfile("assets/GeoLite2-ASN-Test.mmdb").unwrap();
+    /// let config = axum_tariff::Config::new(country_reader).with_asn_reader(asn_reader);
+    /// ```
+    pub fn with_asn_reader(mut self, reader: Reader<Vec<u8>>) -> Self {
+        self.asn_reader = Some(reader);
+        self
+    }
+
+    /// Add an autonomous system number and associated delay to the tariff configuration.
+    ///
+    /// Requires an ASN reader to be configured via [`Config::with_asn_reader`], otherwise
+    /// the entry is ignored when resolving a delay.
+    ///
+    /// # Arguments
+    ///
+    /// * `asn` - An autonomous system number, e.g., 15169 for Google.
+    /// * `delay` - A duration representing how long to delay requests from that ASN.
+    pub fn with_asn(self, asn: u32, delay: Duration) -> Self {
+        self.with_asn_action(asn, Action::Delay(delay))
+    }
+
+    /// Add an autonomous system number and associated [`Action`] to the tariff configuration.
+    ///
+    /// Requires an ASN reader to be configured via [`Config::with_asn_reader`], otherwise
+    /// the entry is ignored when resolving an action.
+    pub fn with_asn_action(mut self, asn: u32, action: Action) -> Self {
+        self.asn_tariffs.insert(asn, action);
+        self
+    }
+
+    /// Add a continent code and associated delay to the tariff configuration.
+    ///
+    /// This uses the two-letter continent code (e.g., "AF", "SA", "EU"). Resolved from the
+    /// same country database passed to [`Config::new`] - no extra reader is required.
+    pub fn with_continent(self, code: &str, delay: Duration) -> Self {
+        self.with_continent_action(code, Action::Delay(delay))
+    }
+
+    /// Add a continent code and associated [`Action`] to the tariff configuration.
+    pub fn with_continent_action(mut self, code: &str, action: Action) -> Self {
+        self.continent_tariffs
+            .insert(Box::from(code.to_uppercase()), action);
+        self
+    }
+
+    /// Provide a GeoLite2-City (or GeoIP2-City) database reader, enabling tariffs keyed by
+    /// first-level subdivision or city.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let country_reader = axum_tariff::Reader::open_readfile("assets/GeoLite2-Country-Test.mmdb").unwrap();
+    /// let city_reader = axum_tariff::Reader::open_readfile("assets/GeoLite2-City-Test.mmdb").unwrap();
+    /// let config = axum_tariff::Config::new(country_reader).with_city_reader(city_reader);
+    /// ```
+    pub fn with_city_reader(mut self, reader: Reader<Vec<u8>>) -> Self {
+        self.city_reader = Some(reader);
+        self
+    }
+
+    /// Add a first-level subdivision ISO code (e.g., "CA" for California) and associated
+    /// delay to the tariff configuration.
+    ///
+    /// Requires a City reader to be configured via [`Config::with_city_reader`], otherwise
+    /// the entry is ignored when resolving an action.
+    pub fn with_subdivision(self, code: &str, delay: Duration) -> Self {
+        self.with_subdivision_action(code, Action::Delay(delay))
+    }
+
+    /// Add a first-level subdivision ISO code and associated [`Action`] to the tariff
+    /// configuration.
+    pub fn with_subdivision_action(mut self, code: &str, action: Action) -> Self {
+        self.subdivision_tariffs
+            .insert(Box::from(code.to_uppercase()), action);
+        self
+    }
+
+    /// Add a city geoname ID and associated delay to the tariff configuration.
+    ///
+    /// Requires a City reader to be configured via [`Config::with_city_reader`], otherwise
+    /// the entry is ignored when resolving an action.
+    pub fn with_city(self, geoname_id: u32, delay: Duration) -> Self {
+        self.with_city_action(geoname_id, Action::Delay(delay))
+    }
+
+    /// Add a city geoname ID and associated [`Action`] to the tariff configuration.
+    pub fn with_city_action(mut self, geoname_id: u32, action: Action) -> Self {
+        self.city_tariffs.insert(geoname_id, action);
+        self
+    }
+
+    /// Choose how a matching country tariff and a matching ASN tariff are combined.
+    ///
+    /// Defaults to [`DelayCombine::Max`].
+    pub fn with_asn_combine(mut self, combine: DelayCombine) -> Self {
+        self.asn_combine = combine;
+        self
+    }
+
+    /// Choose how the client's IP address is resolved from an incoming request.
+    ///
+    /// Defaults to [`ClientIpSource::ConnectInfo`], which cannot be spoofed by the client but
+    /// resolves to the nearest proxy's address rather than the real client when running behind
+    /// one. Pick a header-based variant only when that header is guaranteed to be set (and not
+    /// forwarded verbatim from the client) by infrastructure you trust.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let reader = axum_tariff::Reader::open_readfile("assets/GeoLite2-Country-Test.mmdb").unwrap();
+    /// let config = axum_tariff::Config::new(reader)
+    ///     .with_ip_source(axum_tariff::ClientIpSource::TrustedHops(1));
+    /// ```
+    pub fn with_ip_source(mut self, source: ClientIpSource) -> Self {
+        self.ip_source = source;
+        self
+    }
+
+    /// Set a baseline delay applied to requests that don't match any configured tariff,
+    /// including those whose country/continent/subdivision/city/ASN can't be resolved at all.
+    ///
+    /// Useful as a catch-all throttle, with faster countries carved out via [`Config::with`]
+    /// and friends.
+    pub fn with_default(self, delay: Duration) -> Self {
+        self.with_default_action(Action::Delay(delay))
+    }
+
+    /// Set a baseline [`Action`] applied to requests that don't match any configured tariff.
+    pub fn with_default_action(mut self, action: Action) -> Self {
+        self.default_action = Some(action);
+        self
+    }
+
+    /// Whether loopback, private (RFC 1918), and link-local/unique-local addresses should
+    /// bypass lookup and tariffs entirely, rather than falling back to the default action.
+    ///
+    /// Defaults to `true`, since MaxMind databases never resolve these ranges and internal
+    /// health-checks or load-balancer probes shouldn't be delayed or rejected.
+    pub fn skip_private_ranges(mut self, skip: bool) -> Self {
+        self.skip_private_ranges = skip;
         self
     }
 
@@ -93,24 +360,128 @@ impl Config {
         }
     }
 
-    /// Get the configured delay duration for a given IP address,
-    /// based on its resolved country code.
+    /// Get the configured [`Action`] for a given IP address, based on its resolved geographic
+    /// location (city, subdivision, country, or continent) and, if an ASN reader is
+    /// configured, its resolved autonomous system number.
     ///
-    /// Returns `Some(duration)` if the country has a configured tariff,
-    /// otherwise returns `None`.
-    fn get_delay_for_ip(&self, ip: IpAddr) -> Option<Duration> {
-        self.reader
-            .lookup::<geoip2::Country>(ip)
-            .ok()
-            .flatten()
-            .and_then(|geo| geo.country)
+    /// Returns `None` if the IP is in a private range skipped via
+    /// [`Config::skip_private_ranges`] (default: yes). Otherwise falls back to the action set
+    /// via [`Config::with_default`] if nothing else matches.
+    fn get_action_for_ip(&self, ip: IpAddr) -> Option<Action> {
+        if self.skip_private_ranges && is_private_ip(ip) {
+            return None;
+        }
+
+        let geo_action = self.get_geo_action(ip);
+        let asn_action = self.get_action_for_asn(ip);
+
+        self.combine_actions(geo_action, asn_action)
+            .or_else(|| self.default_action.clone())
+    }
+
+    /// Get the configured [`Action`] for a given IP address, based on its resolved
+    /// geographic location.
+    ///
+    /// Resolves with a most-specific-wins precedence: city > subdivision > country >
+    /// continent. Subdivision and city require a City reader configured via
+    /// [`Config::with_city_reader`]; continent and country are resolved from the database
+    /// passed to [`Config::new`].
+    fn get_geo_action(&self, ip: IpAddr) -> Option<Action> {
+        let country_geo = self.reader.lookup::<geoip2::Country>(ip).ok().flatten();
+
+        let country_action = country_geo
+            .as_ref()
+            .and_then(|geo| geo.country.as_ref())
             .and_then(|country| country.iso_code)
             .and_then(|code| self.tariffs.get(code.to_uppercase().as_str()))
+            .cloned();
+
+        let continent_action = country_geo
+            .as_ref()
+            .and_then(|geo| geo.continent.as_ref())
+            .and_then(|continent| continent.code)
+            .and_then(|code| self.continent_tariffs.get(code.to_uppercase().as_str()))
+            .cloned();
+
+        let (subdivision_action, city_action) = self.get_city_actions(ip);
+
+        city_action
+            .or(subdivision_action)
+            .or(country_action)
+            .or(continent_action)
+    }
+
+    /// Get the configured subdivision and city [`Action`]s for a given IP address.
+    ///
+    /// Returns `(None, None)` if no City reader is configured or the lookup fails.
+    fn get_city_actions(&self, ip: IpAddr) -> (Option<Action>, Option<Action>) {
+        let Some(city_reader) = self.city_reader.as_ref() else {
+            return (None, None);
+        };
+        let Some(geo) = city_reader.lookup::<geoip2::City>(ip).ok().flatten() else {
+            return (None, None);
+        };
+
+        let subdivision_action = geo
+            .subdivisions
+            .as_deref()
+            .and_then(|subdivisions| subdivisions.first())
+            .and_then(|subdivision| subdivision.iso_code)
+            .and_then(|code| self.subdivision_tariffs.get(code.to_uppercase().as_str()))
+            .cloned();
+
+        let city_action = geo
+            .city
+            .as_ref()
+            .and_then(|city| city.geoname_id)
+            .and_then(|geoname_id| self.city_tariffs.get(&geoname_id))
+            .cloned();
+
+        (subdivision_action, city_action)
+    }
+
+    /// Get the configured [`Action`] for a given IP address, based on its resolved autonomous
+    /// system number.
+    ///
+    /// Returns `None` if no ASN reader is configured, the lookup fails,
+    /// or the resolved ASN has no configured tariff.
+    fn get_action_for_asn(&self, ip: IpAddr) -> Option<Action> {
+        let asn_reader = self.asn_reader.as_ref()?;
+        asn_reader
+            .lookup::<geoip2::Asn>(ip)
+            .ok()
+            .flatten()
+            .and_then(|asn| asn.autonomous_system_number)
+            .and_then(|number| self.asn_tariffs.get(&number))
             .cloned()
     }
+
+    /// Combine a geographic action (continent/country/subdivision/city) and an ASN action
+    /// when both match a request.
+    ///
+    /// Two delays are merged according to [`Config::with_asn_combine`]; otherwise the more
+    /// severe action wins (see [`Action::severity`]), so a reject from either source always
+    /// takes precedence over a plain delay from the other.
+    fn combine_actions(&self, a: Option<Action>, b: Option<Action>) -> Option<Action> {
+        match (a, b) {
+            (Some(Action::Delay(a)), Some(Action::Delay(b))) => {
+                Some(Action::Delay(self.asn_combine.apply(a, b)))
+            }
+            (Some(a), Some(b)) => {
+                if a.severity() >= b.severity() {
+                    Some(a)
+                } else {
+                    Some(b)
+                }
+            }
+            (Some(a), None) => Some(a),
+            (None, Some(b)) => Some(b),
+            (None, None) => None,
+        }
+    }
 }
 
-/// A `tower::Layer` that wraps services to apply country-based request delays.
+/// A `tower::Layer` that wraps services to apply country-based tariff actions.
 ///
 /// Can be applied to an Axum router using `.layer(...)`.
 #[derive(Clone)]
@@ -129,10 +500,10 @@ impl<S> tower::Layer<S> for TariffLayer {
     }
 }
 
-/// A `tower::Service` that introduces delay based on the client IP address's country.
+/// A `tower::Service` that applies a tariff action based on the client IP address's country.
 ///
-/// It uses the MaxMind GeoIP database to look up the country, and delays the request
-/// if the country has a configured tariff.
+/// It uses the MaxMind GeoIP database to look up the country, and applies the configured
+/// [`Action`] (delaying, rejecting, or both) if the country has a configured tariff.
 #[derive(Clone)]
 pub struct TariffService<S> {
     inner: S,
@@ -141,7 +512,7 @@ pub struct TariffService<S> {
 
 impl<S, B> Service<Request<B>> for TariffService<S>
 where
-    B: Send + 'static,
+    B: Send + 'static + Default + From<Bytes>,
     S: Clone,
     S: Service<Request<B>, Response = Response<B>> + Send + 'static,
     S::Future: Send + 'static,
@@ -157,35 +528,131 @@ where
     fn call(&mut self, req: Request<B>) -> Self::Future {
         let mut inner = self.inner.clone();
         let config = Arc::clone(&self.config);
-        let client_ip = extract_client_ip(&req);
+        let client_ip = extract_client_ip(&req, &config.ip_source);
+        let action = client_ip
+            .map(|ip| config.get_action_for_ip(ip))
+            .unwrap_or_else(|| config.default_action.clone());
 
         Box::pin(async move {
-            if let Some(delay) = client_ip.and_then(|ip| config.get_delay_for_ip(ip)) {
-                tokio::time::sleep(delay).await;
+            match action {
+                Some(Action::Delay(delay)) => {
+                    tokio::time::sleep(delay).await;
+                    inner.call(req).await
+                }
+                Some(Action::Reject(status, body)) => Ok(reject_response(status, body)),
+                Some(Action::DelayThen(delay, status)) => {
+                    tokio::time::sleep(delay).await;
+                    Ok(reject_response(status, None))
+                }
+                None => inner.call(req).await,
             }
-
-            inner.call(req).await
         })
     }
 }
 
-/// Extract the client's IP address from headers or socket address.
+/// Build a response that short-circuits the inner service, used for [`Action::Reject`] and
+/// [`Action::DelayThen`].
+fn reject_response<B: Default + From<Bytes>>(
+    status: StatusCode,
+    body: Option<Bytes>,
+) -> Response<B> {
+    let mut response = Response::new(body.map(B::from).unwrap_or_default());
+    *response.status_mut() = status;
+    response
+}
+
+/// Whether `ip` is a loopback, private (RFC 1918), or link-local/unique-local address.
 ///
-/// Tries `X-Forwarded-For` header first, then falls back to `ConnectInfo`.
-fn extract_client_ip<B>(req: &Request<B>) -> Option<IpAddr> {
-    if let Some(header) = req.headers().get("x-forwarded-for") {
-        if let Ok(ip_str) = header.to_str() {
-            if let Some(ip_str) = ip_str.split(',').next() {
-                return ip_str.trim().parse().ok();
-            }
+/// MaxMind databases never resolve these ranges, so there's no geographic tariff to apply
+/// anyway - this just lets [`Config::skip_private_ranges`] short-circuit the lookup.
+fn is_private_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => ip.is_loopback() || ip.is_private() || ip.is_link_local(),
+        IpAddr::V6(ip) => {
+            ip.is_loopback()
+                || (ip.segments()[0] & 0xfe00) == 0xfc00 // unique local, fc00::/7
+                || (ip.segments()[0] & 0xffc0) == 0xfe80 // link-local, fe80::/10
         }
     }
+}
+
+/// Extract the client's IP address from the request, according to the configured
+/// [`ClientIpSource`].
+fn extract_client_ip<B>(req: &Request<B>, source: &ClientIpSource) -> Option<IpAddr> {
+    match source {
+        ClientIpSource::ConnectInfo => connect_info_ip(req),
+        ClientIpSource::RightmostForwardedFor => forwarded_for_hop(req, 0),
+        ClientIpSource::TrustedHops(n) => forwarded_for_hop(req, *n),
+        ClientIpSource::XRealIp => header_ip(req, "x-real-ip"),
+        ClientIpSource::Forwarded => forwarded_header_ip(req),
+    }
+}
 
+/// Read the client IP from the `ConnectInfo` request extension.
+fn connect_info_ip<B>(req: &Request<B>) -> Option<IpAddr> {
     req.extensions()
         .get::<axum::extract::connect_info::ConnectInfo<SocketAddr>>()
         .map(|info| info.0.ip())
 }
 
+/// Parse a single-IP header such as `X-Real-IP`.
+fn header_ip<B>(req: &Request<B>, name: &str) -> Option<IpAddr> {
+    req.headers()
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse().ok())
+}
+
+/// Take the entry `hops` positions from the right of the `X-Forwarded-For` header, i.e. the
+/// address appended by the `hops`-th trusted proxy counting from this service. `hops = 0` is
+/// the rightmost (and therefore closest, most trustworthy) entry.
+fn forwarded_for_hop<B>(req: &Request<B>, hops: usize) -> Option<IpAddr> {
+    let header = req.headers().get("x-forwarded-for")?;
+    let value = header.to_str().ok()?;
+    value
+        .rsplit(',')
+        .nth(hops)
+        .and_then(|ip_str| ip_str.trim().parse().ok())
+}
+
+/// Parse the `Forwarded` header (RFC 7239) and return the IP from the last `for=` token, which
+/// is the one appended by the proxy closest to this service.
+fn forwarded_header_ip<B>(req: &Request<B>) -> Option<IpAddr> {
+    let header = req.headers().get("forwarded")?;
+    let value = header.to_str().ok()?;
+
+    value
+        .split(';')
+        .flat_map(|segment| segment.split(','))
+        .filter_map(|token| {
+            let token = token.trim();
+            token
+                .strip_prefix("for=")
+                .or_else(|| token.strip_prefix("For="))
+        })
+        .last()
+        .and_then(parse_forwarded_for_value)
+}
+
+/// Parse a single RFC 7239 `for=` value, stripping quotes, an optional `"[...]"` wrapping an
+/// IPv6 literal, and an optional trailing `:port`.
+fn parse_forwarded_for_value(value: &str) -> Option<IpAddr> {
+    let value = value.trim().trim_matches('"');
+
+    if let Some(rest) = value.strip_prefix('[') {
+        // Bracketed IPv6 literal, e.g. `[2001:db8::1]` or `[2001:db8::1]:8080`.
+        let end = rest.find(']')?;
+        return rest[..end].parse().ok();
+    }
+
+    if value.matches(':').count() == 1 {
+        // A single colon means IPv4 with a port, e.g. `192.0.2.1:8080`.
+        return value.split(':').next()?.parse().ok();
+    }
+
+    value.parse().ok()
+}
+
 #[cfg(test)]
 mod tests {
     use std::net::SocketAddr;
@@ -208,20 +675,176 @@ mod tests {
             .expect("You need the test MaxMind DB at assets/GeoLite2-Country-Test.mmdb")
     }
 
+    fn asn_test_reader() -> Reader<Vec<u8>> {
+        Reader::open_readfile("assets/GeoLite2-ASN-Test.mmdb")
+            .expect("You need the test MaxMind DB at assets/GeoLite2-ASN-Test.mmdb")
+    }
+
+    fn city_test_reader() -> Reader<Vec<u8>> {
+        Reader::open_readfile("assets/GeoLite2-City-Test.mmdb")
+            .expect("You need the test MaxMind DB at assets/GeoLite2-City-Test.mmdb")
+    }
+
     #[tokio::test]
     async fn test_tariff_config_basic_mapping() {
         let config = Config::new(test_reader()).with(IP_REGION, Duration::from_millis(1234));
 
         let ip: IpAddr = IP_TEST.parse().unwrap();
-        let delay = config.get_delay_for_ip(ip);
+        let action = config.get_action_for_ip(ip);
 
-        assert_eq!(delay, Some(Duration::from_millis(1234)));
+        assert_eq!(action, Some(Action::Delay(Duration::from_millis(1234))));
+    }
+
+    #[tokio::test]
+    async fn test_tariff_config_asn_mapping() {
+        const IP_ASN: &str = "1.128.0.0";
+        const ASN: u32 = 1221;
+
+        let config = Config::new(test_reader())
+            .with_asn_reader(asn_test_reader())
+            .with_asn(ASN, Duration::from_millis(500));
+
+        let ip: IpAddr = IP_ASN.parse().unwrap();
+        let action = config.get_action_for_ip(ip);
+
+        assert_eq!(action, Some(Action::Delay(Duration::from_millis(500))));
+    }
+
+    #[tokio::test]
+    async fn test_tariff_config_combines_country_and_asn_delays() {
+        const IP_ASN: &str = "1.128.0.0";
+        const ASN: u32 = 1221;
+
+        let config = Config::new(test_reader())
+            .with(IP_REGION, Duration::from_millis(100))
+            .with_asn_reader(asn_test_reader())
+            .with_asn(ASN, Duration::from_millis(500));
+
+        // The GB test IP and the AS1221 test IP are different addresses, so combine the
+        // country action of one with the ASN action of the other.
+        let action = config.combine_actions(
+            config.get_geo_action(IP_TEST.parse().unwrap()),
+            config.get_action_for_asn(IP_ASN.parse().unwrap()),
+        );
+
+        // Max combine (the default) keeps the larger of the two delays.
+        assert_eq!(action, Some(Action::Delay(Duration::from_millis(500))));
+    }
+
+    #[tokio::test]
+    async fn test_tariff_config_reject_outranks_delay() {
+        const IP_ASN: &str = "1.128.0.0";
+        const ASN: u32 = 1221;
+
+        let config = Config::new(test_reader())
+            .with(IP_REGION, Duration::from_millis(100))
+            .with_asn_reader(asn_test_reader())
+            .with_asn_action(ASN, Action::Reject(StatusCode::FORBIDDEN, None));
+
+        let action = config.combine_actions(
+            config.get_geo_action(IP_TEST.parse().unwrap()),
+            config.get_action_for_asn(IP_ASN.parse().unwrap()),
+        );
+
+        assert_eq!(action, Some(Action::Reject(StatusCode::FORBIDDEN, None)));
+    }
+
+    #[tokio::test]
+    async fn test_tariff_config_continent_mapping() {
+        const IP_CONTINENT: &str = "EU";
+
+        let config =
+            Config::new(test_reader()).with_continent(IP_CONTINENT, Duration::from_millis(50));
+
+        let ip: IpAddr = IP_TEST.parse().unwrap();
+        let action = config.get_action_for_ip(ip);
+
+        assert_eq!(action, Some(Action::Delay(Duration::from_millis(50))));
+    }
+
+    #[tokio::test]
+    async fn test_tariff_config_subdivision_outranks_country() {
+        const SUBDIVISION: &str = "ENG";
+
+        let config = Config::new(test_reader())
+            .with(IP_REGION, Duration::from_millis(100))
+            .with_city_reader(city_test_reader())
+            .with_subdivision(SUBDIVISION, Duration::from_millis(300));
+
+        let ip: IpAddr = IP_TEST.parse().unwrap();
+        let action = config.get_action_for_ip(ip);
+
+        assert_eq!(action, Some(Action::Delay(Duration::from_millis(300))));
+    }
+
+    #[tokio::test]
+    async fn test_tariff_config_city_outranks_subdivision() {
+        const SUBDIVISION: &str = "ENG";
+        const CITY_GEONAME_ID: u32 = 2655045; // Boxford, England
+
+        let config = Config::new(test_reader())
+            .with_city_reader(city_test_reader())
+            .with_subdivision(SUBDIVISION, Duration::from_millis(300))
+            .with_city(CITY_GEONAME_ID, Duration::from_millis(700));
+
+        let ip: IpAddr = IP_TEST.parse().unwrap();
+        let action = config.get_action_for_ip(ip);
+
+        assert_eq!(action, Some(Action::Delay(Duration::from_millis(700))));
+    }
+
+    #[tokio::test]
+    async fn test_tariff_config_default_action_fallback() {
+        const IP_NO_TARIFF: &str = "176.98.0.0"; // FR, no configured tariff
+
+        let config = Config::new(test_reader())
+            .with(IP_REGION, Duration::from_millis(100))
+            .with_default(Duration::from_millis(10));
+
+        let ip: IpAddr = IP_NO_TARIFF.parse().unwrap();
+        let action = config.get_action_for_ip(ip);
+
+        assert_eq!(action, Some(Action::Delay(Duration::from_millis(10))));
+    }
+
+    #[tokio::test]
+    async fn test_tariff_config_matched_tariff_outranks_default() {
+        let config = Config::new(test_reader())
+            .with(IP_REGION, Duration::from_millis(100))
+            .with_default(Duration::from_millis(10));
+
+        let ip: IpAddr = IP_TEST.parse().unwrap();
+        let action = config.get_action_for_ip(ip);
+
+        assert_eq!(action, Some(Action::Delay(Duration::from_millis(100))));
+    }
+
+    #[tokio::test]
+    async fn test_tariff_config_skips_private_ranges_by_default() {
+        let config = Config::new(test_reader()).with_default(Duration::from_millis(10));
+
+        let ip: IpAddr = "192.168.1.1".parse().unwrap();
+        assert_eq!(config.get_action_for_ip(ip), None);
+    }
+
+    #[tokio::test]
+    async fn test_tariff_config_applies_default_to_private_ranges_when_disabled() {
+        let config = Config::new(test_reader())
+            .with_default(Duration::from_millis(10))
+            .skip_private_ranges(false);
+
+        let ip: IpAddr = "192.168.1.1".parse().unwrap();
+        assert_eq!(
+            config.get_action_for_ip(ip),
+            Some(Action::Delay(Duration::from_millis(10)))
+        );
     }
 
     #[tokio::test]
     async fn test_middleware_applies_delay() {
         let layer = Config::new(test_reader())
             .with(IP_REGION, Duration::from_millis(200))
+            .with_ip_source(ClientIpSource::RightmostForwardedFor)
             .into_layer();
 
         let app = Router::new()
@@ -246,24 +869,164 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn test_extract_ip_header_and_fallback() {
-        // Header parsing
+    async fn test_middleware_rejects_without_calling_inner() {
+        let layer = Config::new(test_reader())
+            .with_action(IP_REGION, Action::Reject(StatusCode::FORBIDDEN, None))
+            .with_ip_source(ClientIpSource::RightmostForwardedFor)
+            .into_layer();
+
+        let app = Router::new()
+            .route("/", get(|| async { panic!("inner service must not be called") }))
+            .layer(layer)
+            .with_state(());
+
+        let addr: SocketAddr = "127.0.0.1:12345".parse().unwrap();
         let req = Request::builder()
-            .header("x-forwarded-for", "8.8.8.8")
-            .body(())
+            .uri("/")
+            .header("x-forwarded-for", IP_TEST) // GB IP
+            .extension(ConnectInfo(addr))
+            .body(Body::empty())
             .unwrap();
 
-        assert_eq!(
-            extract_client_ip(&req),
-            Some("8.8.8.8".parse::<IpAddr>().unwrap())
-        );
+        let response = app.clone().oneshot(req).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn test_middleware_delays_then_rejects() {
+        let layer = Config::new(test_reader())
+            .with_action(
+                IP_REGION,
+                Action::DelayThen(Duration::from_millis(200), StatusCode::TOO_MANY_REQUESTS),
+            )
+            .with_ip_source(ClientIpSource::RightmostForwardedFor)
+            .into_layer();
+
+        let app = Router::new()
+            .route("/", get(|| async { panic!("inner service must not be called") }))
+            .layer(layer)
+            .with_state(());
+
+        let addr: SocketAddr = "127.0.0.1:12345".parse().unwrap();
+        let req = Request::builder()
+            .uri("/")
+            .header("x-forwarded-for", IP_TEST) // GB IP
+            .extension(ConnectInfo(addr))
+            .body(Body::empty())
+            .unwrap();
+
+        let start = Instant::now();
+        let response = app.clone().oneshot(req).await.unwrap();
+        let elapsed = start.elapsed();
+
+        assert!(elapsed >= Duration::from_millis(180)); // Allow for small overhead
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert!(body.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_middleware_applies_default_when_client_ip_unresolvable() {
+        let layer = Config::new(test_reader())
+            .with(IP_REGION, Duration::from_millis(100))
+            .with_default(Duration::from_millis(10))
+            .with_ip_source(ClientIpSource::XRealIp)
+            .into_layer();
+
+        let app = Router::new()
+            .route("/", get(|| async { "ok" }))
+            .layer(layer)
+            .with_state(());
+
+        // No `ConnectInfo` extension and no `X-Real-IP` header, so the client IP can't be
+        // resolved at all.
+        let req = Request::builder()
+            .uri("/")
+            .body(Body::empty())
+            .unwrap();
+
+        let start = Instant::now();
+        let response = app.clone().oneshot(req).await.unwrap();
+        let elapsed = start.elapsed();
 
-        // Fallback to ConnectInfo
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(elapsed >= Duration::from_millis(5)); // the default delay was applied
+    }
+
+    #[tokio::test]
+    async fn test_extract_ip_connect_info() {
         let mut req = Request::builder().body(()).unwrap();
         let addr: SocketAddr = "192.168.1.1:1234".parse().unwrap();
         req.extensions_mut().insert(ConnectInfo(addr));
 
-        let ip = extract_client_ip(&req);
+        let ip = extract_client_ip(&req, &ClientIpSource::ConnectInfo);
         assert_eq!(ip, Some(addr.ip()));
+
+        // A forged X-Forwarded-For header is ignored.
+        req.headers_mut()
+            .insert("x-forwarded-for", "8.8.8.8".parse().unwrap());
+        assert_eq!(
+            extract_client_ip(&req, &ClientIpSource::ConnectInfo),
+            Some(addr.ip())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_extract_ip_rightmost_forwarded_for() {
+        let req = Request::builder()
+            .header("x-forwarded-for", "1.1.1.1, 8.8.8.8, 9.9.9.9")
+            .body(())
+            .unwrap();
+
+        // The rightmost entry is the one appended by the proxy closest to us.
+        assert_eq!(
+            extract_client_ip(&req, &ClientIpSource::RightmostForwardedFor),
+            Some("9.9.9.9".parse::<IpAddr>().unwrap())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_extract_ip_trusted_hops() {
+        let req = Request::builder()
+            .header("x-forwarded-for", "1.1.1.1, 8.8.8.8, 9.9.9.9")
+            .body(())
+            .unwrap();
+
+        assert_eq!(
+            extract_client_ip(&req, &ClientIpSource::TrustedHops(1)),
+            Some("8.8.8.8".parse::<IpAddr>().unwrap())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_extract_ip_x_real_ip() {
+        let req = Request::builder()
+            .header("x-real-ip", "8.8.8.8")
+            .body(())
+            .unwrap();
+
+        assert_eq!(
+            extract_client_ip(&req, &ClientIpSource::XRealIp),
+            Some("8.8.8.8".parse::<IpAddr>().unwrap())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_extract_ip_forwarded_header() {
+        let req = Request::builder()
+            .header(
+                "forwarded",
+                "for=1.1.1.1, for=\"[2001:db8:cafe::17]:4711\"",
+            )
+            .body(())
+            .unwrap();
+
+        assert_eq!(
+            extract_client_ip(&req, &ClientIpSource::Forwarded),
+            Some("2001:db8:cafe::17".parse::<IpAddr>().unwrap())
+        );
     }
 }